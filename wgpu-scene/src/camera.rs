@@ -1,3 +1,5 @@
+use std::time::Instant;
+
 use cgmath::prelude::*;
 use cgmath::{Matrix4, Point3, Vector3};
 use winit::event::{DeviceEvent, ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
@@ -60,7 +62,7 @@ impl Camera {
     }
 
     pub fn set_fov_y(&mut self, fov_y: f32) {
-        self.fov_y = fov_y.clamp(1.0f32.to_radians(), 45.0f32.to_radians());
+        self.fov_y = fov_y.clamp(1.0, 45.0);
     }
 
     fn update_vectors(&mut self) {
@@ -102,8 +104,10 @@ impl CameraUniform {
 }
 
 pub struct CameraController {
-    move_speed: f32,
+    thrust_mag: f32,
+    damping_coeff: f32,
     look_speed: f32,
+    zoom_speed: f32,
     is_forward_pressed: bool,
     is_backward_pressed: bool,
     is_left_pressed: bool,
@@ -111,13 +115,21 @@ pub struct CameraController {
     is_down_pressed: bool,
     is_up_pressed: bool,
     cursor_move: Option<(f32, f32)>,
+    pending_zoom: f32,
+    velocity: Vector3<f32>,
+    last_update: Instant,
 }
 
 impl CameraController {
-    pub fn new(move_speed: f32, look_speed: f32) -> Self {
+    /// `half_life` is the time in seconds it takes the camera's velocity to
+    /// decay to half its value once thrust stops, which gives a well-defined
+    /// top speed of `thrust_mag / damping_coeff`.
+    pub fn new(thrust_mag: f32, half_life: f32, look_speed: f32, zoom_speed: f32) -> Self {
         Self {
-            move_speed,
+            thrust_mag,
+            damping_coeff: f32::ln(2.0) / half_life,
             look_speed,
+            zoom_speed,
             is_forward_pressed: false,
             is_backward_pressed: false,
             is_left_pressed: false,
@@ -125,6 +137,9 @@ impl CameraController {
             is_down_pressed: false,
             is_up_pressed: false,
             cursor_move: None,
+            pending_zoom: 0.0,
+            velocity: Vector3::new(0.0, 0.0, 0.0),
+            last_update: Instant::now(),
         }
     }
 
@@ -168,6 +183,13 @@ impl CameraController {
                     _ => false,
                 }
             }
+            WindowEvent::MouseWheel { delta, .. } => {
+                self.pending_zoom += match delta {
+                    winit::event::MouseScrollDelta::LineDelta(_, y) => *y,
+                    winit::event::MouseScrollDelta::PixelDelta(position) => position.y as f32,
+                };
+                true
+            }
             _ => false,
         }
     }
@@ -186,28 +208,48 @@ impl CameraController {
     }
 
     pub fn update_camera(&mut self, camera: &mut Camera) {
+        let now = Instant::now();
+        // Clamp dt so a stall before the first frame (or any later hitch,
+        // e.g. asset loading) can't integrate a huge one-frame position jump.
+        let dt = (now - self.last_update).as_secs_f32().min(0.1);
+        self.last_update = now;
+
+        let mut thrust_dir = Vector3::new(0.0, 0.0, 0.0);
         if self.is_forward_pressed {
-            camera.position += camera.front * self.move_speed;
+            thrust_dir += camera.front;
         }
         if self.is_backward_pressed {
-            camera.position += camera.front * -self.move_speed;
+            thrust_dir -= camera.front;
         }
         if self.is_right_pressed {
-            camera.position += camera.right * self.move_speed;
+            thrust_dir += camera.right;
         }
         if self.is_left_pressed {
-            camera.position += camera.right * -self.move_speed;
+            thrust_dir -= camera.right;
+        }
+        if self.is_up_pressed {
+            thrust_dir += camera.up;
         }
         if self.is_down_pressed {
-            camera.position += camera.up * -self.move_speed;
+            thrust_dir -= camera.up;
         }
-        if self.is_up_pressed {
-            camera.position += camera.up * self.move_speed;
+        if thrust_dir.magnitude2() > 0.0 {
+            thrust_dir = thrust_dir.normalize();
         }
+
+        let acceleration = thrust_dir * self.thrust_mag - self.velocity * self.damping_coeff;
+        self.velocity += acceleration * dt;
+        camera.position += self.velocity * dt;
+
         if let Some((delta_yaw, delta_pitch)) = self.cursor_move {
             camera.increment_yaw(delta_yaw);
             camera.increment_pitch(delta_pitch);
             self.cursor_move = None;
         }
+
+        if self.pending_zoom != 0.0 {
+            camera.set_fov_y(camera.fov_y - self.pending_zoom * self.zoom_speed);
+            self.pending_zoom = 0.0;
+        }
     }
 }
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use cgmath::{InnerSpace, Vector3};
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
+use wgpu::util::DeviceExt;
+
+use crate::marching_cubes_tables::{EDGE_TABLE, TRI_TABLE};
+use crate::model::{Material, Mesh, Model, ModelVertex};
+use crate::resources::{compute_normals, compute_tangents, load_texture};
+
+/// Offsets of a voxel's 8 corners, indexed the same way as `EDGE_TABLE`/`TRI_TABLE`.
+const CORNER_OFFSETS: [(u32, u32, u32); 8] = [
+    (0, 0, 0),
+    (1, 0, 0),
+    (1, 1, 0),
+    (0, 1, 0),
+    (0, 0, 1),
+    (1, 0, 1),
+    (1, 1, 1),
+    (0, 1, 1),
+];
+
+/// Pairs of corner indices making up each of a voxel's 12 edges, same order as `EDGE_TABLE`'s bits.
+const EDGE_CORNERS: [(usize, usize); 12] = [
+    (0, 1),
+    (1, 2),
+    (2, 3),
+    (3, 0),
+    (4, 5),
+    (5, 6),
+    (6, 7),
+    (7, 4),
+    (0, 4),
+    (1, 5),
+    (2, 6),
+    (3, 7),
+];
+
+/// A dense 3D scalar field sampled on an `(size + 1)^3` grid of voxel corners.
+struct ScalarField {
+    size: u32,
+    values: Vec<f32>,
+}
+
+impl ScalarField {
+    fn sample_at(&self, x: u32, y: u32, z: u32) -> f32 {
+        let stride = self.size + 1;
+        self.values[(z * stride * stride + y * stride + x) as usize]
+    }
+
+    fn corner_position(&self, x: u32, y: u32, z: u32) -> Vector3<f32> {
+        Vector3::new(x as f32, y as f32, z as f32)
+    }
+}
+
+/// Interpolates the point on an edge where the field crosses `isolevel`.
+fn interpolate_edge(
+    p1: Vector3<f32>,
+    v1: f32,
+    p2: Vector3<f32>,
+    v2: f32,
+    isolevel: f32,
+) -> Vector3<f32> {
+    if (v2 - v1).abs() < f32::EPSILON {
+        return p1;
+    }
+    let t = (isolevel - v1) / (v2 - v1);
+    p1 + (p2 - p1) * t
+}
+
+/// Polygonises `field` against `isolevel` with the standard marching cubes
+/// tables, returning per-vertex positions alongside the triangle index list.
+fn march_cubes(field: &ScalarField, isolevel: f32) -> (Vec<Vector3<f32>>, Vec<u32>) {
+    let mut positions = Vec::new();
+    let mut indices = Vec::new();
+
+    // Every cube edge is uniquely identified by the pair of grid corners it
+    // connects; caching on that exact integer key welds the vertex shared by
+    // every triangle (in this voxel and its neighbours) that crosses the same
+    // edge, so the tangent/normal averaging passes have something to average.
+    let mut edge_cache: HashMap<[(u32, u32, u32); 2], u32> = HashMap::new();
+
+    for z in 0..field.size {
+        for y in 0..field.size {
+            for x in 0..field.size {
+                let global_corner: Vec<(u32, u32, u32)> = CORNER_OFFSETS
+                    .iter()
+                    .map(|(dx, dy, dz)| (x + dx, y + dy, z + dz))
+                    .collect();
+                let corner_pos: Vec<Vector3<f32>> = global_corner
+                    .iter()
+                    .map(|&(cx, cy, cz)| field.corner_position(cx, cy, cz))
+                    .collect();
+                let corner_val: Vec<f32> = global_corner
+                    .iter()
+                    .map(|&(cx, cy, cz)| field.sample_at(cx, cy, cz))
+                    .collect();
+
+                let mut cube_index = 0u8;
+                for (i, &v) in corner_val.iter().enumerate() {
+                    if v < isolevel {
+                        cube_index |= 1 << i;
+                    }
+                }
+
+                let edge_mask = EDGE_TABLE[cube_index as usize];
+                if edge_mask == 0 {
+                    continue;
+                }
+
+                let mut edge_vertex_index = [0u32; 12];
+                for (edge, &(a, b)) in EDGE_CORNERS.iter().enumerate() {
+                    if edge_mask & (1 << edge) == 0 {
+                        continue;
+                    }
+
+                    let mut key = [global_corner[a], global_corner[b]];
+                    key.sort_unstable();
+
+                    edge_vertex_index[edge] = *edge_cache.entry(key).or_insert_with(|| {
+                        let vertex = interpolate_edge(
+                            corner_pos[a],
+                            corner_val[a],
+                            corner_pos[b],
+                            corner_val[b],
+                            isolevel,
+                        );
+                        positions.push(vertex);
+                        (positions.len() - 1) as u32
+                    });
+                }
+
+                for triangle in TRI_TABLE[cube_index as usize].chunks(3) {
+                    if triangle[0] < 0 {
+                        break;
+                    }
+                    for &edge in triangle {
+                        indices.push(edge_vertex_index[edge as usize]);
+                    }
+                }
+            }
+        }
+    }
+
+    (positions, indices)
+}
+
+/// Fills an `(size + 1)^3` grid with fractal Perlin noise minus a height bias
+/// that rises linearly from 0 at `y = 0` to 1 at `y = size`.
+fn sample_terrain_field(size: u32, seed: u32) -> ScalarField {
+    let fbm = Fbm::<Perlin>::new(seed).set_octaves(4).set_persistence(0.5);
+    let stride = size + 1;
+    let scale = 1.0 / size as f64;
+
+    let mut values = Vec::with_capacity((stride * stride * stride) as usize);
+    for z in 0..stride {
+        for y in 0..stride {
+            for x in 0..stride {
+                let noise = fbm.get([x as f64 * scale, y as f64 * scale, z as f64 * scale]);
+                // Bias the field downward with height so the surface settles
+                // into rolling terrain instead of floating noise.
+                let height_bias = y as f32 / size as f32;
+                values.push(noise as f32 - height_bias);
+            }
+        }
+    }
+
+    ScalarField { size, values }
+}
+
+/// Projects a vertex onto the plane perpendicular to whichever axis its
+/// normal points along most, instead of always flattening onto XZ.
+fn dominant_axis_uv(position: Vector3<f32>, normal: Vector3<f32>) -> [f32; 2] {
+    let abs = Vector3::new(normal.x.abs(), normal.y.abs(), normal.z.abs());
+    if abs.x >= abs.y && abs.x >= abs.z {
+        [position.y, position.z]
+    } else if abs.y >= abs.z {
+        [position.x, position.z]
+    } else {
+        [position.x, position.y]
+    }
+}
+
+/// Generates a procedural terrain mesh by running marching cubes over a
+/// fractal Perlin noise field, returned as a [`Model`] like any other asset.
+pub async fn generate_terrain(
+    size: u32,
+    seed: u32,
+    isolevel: f32,
+    diffuse_texture_path: &Path,
+    normal_texture_path: &Path,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    layout: &wgpu::BindGroupLayout,
+) -> anyhow::Result<Model> {
+    let field = sample_terrain_field(size, seed);
+    let (positions, indices) = march_cubes(&field, isolevel);
+
+    let mut vertices = positions
+        .iter()
+        .map(|p| ModelVertex {
+            position: (*p).into(),
+            tex_coords: [0.0, 0.0],
+            normal: [0.0, 1.0, 0.0],
+            tangent: [0.0; 3],
+            bitangent: [0.0; 3],
+        })
+        .collect::<Vec<_>>();
+
+    compute_normals(&mut vertices, &indices);
+
+    for vertex in &mut vertices {
+        vertex.tex_coords = dominant_axis_uv(vertex.position.into(), vertex.normal.into());
+    }
+
+    compute_tangents(&mut vertices, &indices);
+
+    let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Terrain Vertex Buffer"),
+        contents: bytemuck::cast_slice(&vertices),
+        usage: wgpu::BufferUsages::VERTEX,
+    });
+    let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Terrain Index Buffer"),
+        contents: bytemuck::cast_slice(&indices),
+        usage: wgpu::BufferUsages::INDEX,
+    });
+
+    let diffuse_texture = load_texture(diffuse_texture_path, false, device, queue).await?;
+    let normal_texture = load_texture(normal_texture_path, true, device, queue).await?;
+    let material = Material::new(device, "Terrain", diffuse_texture, normal_texture, layout);
+
+    let mesh = Mesh {
+        name: "Terrain".to_string(),
+        vertex_buffer,
+        index_buffer,
+        num_elements: indices.len() as u32,
+        material: 0,
+    };
+
+    Ok(Model {
+        meshes: vec![mesh],
+        materials: vec![material],
+    })
+}
@@ -5,6 +5,7 @@ use cfg_if::cfg_if;
 use wgpu::util::DeviceExt;
 
 use crate::model::{Material, Mesh, Model, ModelVertex};
+use crate::skybox::Cubemap;
 use crate::texture::Texture;
 
 #[cfg(target_arch = "wasm32")]
@@ -79,11 +80,127 @@ pub async fn load_texture(
     )
 }
 
+/// Face order is +X, -X, +Y, -Y, +Z, -Z.
+pub async fn load_cubemap(
+    face_file_names: [&Path; 6],
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+) -> anyhow::Result<Cubemap> {
+    let mut faces = Vec::with_capacity(face_file_names.len());
+    for file_name in face_file_names {
+        faces.push(load_binary(file_name).await?);
+    }
+
+    let faces: [Vec<u8>; 6] = faces.try_into().unwrap();
+    Cubemap::from_face_bytes(device, queue, &faces, "Skybox")
+}
+
+/// Averages face normals onto each vertex they touch.
+pub(crate) fn compute_normals(vertices: &mut [ModelVertex], indices: &[u32]) {
+    let mut accum = vec![cgmath::Vector3::new(0.0, 0.0, 0.0); vertices.len()];
+
+    for chunk in indices.chunks(3) {
+        let p0: cgmath::Vector3<f32> = vertices[chunk[0] as usize].position.into();
+        let p1: cgmath::Vector3<f32> = vertices[chunk[1] as usize].position.into();
+        let p2: cgmath::Vector3<f32> = vertices[chunk[2] as usize].position.into();
+
+        let face_normal = (p1 - p0).cross(p2 - p0);
+        for &i in chunk {
+            accum[i as usize] += face_normal;
+        }
+    }
+
+    for (vertex, normal) in vertices.iter_mut().zip(accum) {
+        vertex.normal = normal.normalize().into();
+    }
+}
+
+/// Averages face tangents/bitangents onto each vertex they touch, from the
+/// UV-space derivative of the triangle's positions.
+pub(crate) fn compute_tangents(vertices: &mut [ModelVertex], indices: &[u32]) {
+    let mut triangles_included = vec![0_u32; vertices.len()];
+
+    for chunk in indices.chunks(3) {
+        let v0 = vertices[chunk[0] as usize];
+        let v1 = vertices[chunk[1] as usize];
+        let v2 = vertices[chunk[2] as usize];
+
+        let pos0: cgmath::Vector3<f32> = v0.position.into();
+        let pos1: cgmath::Vector3<f32> = v1.position.into();
+        let pos2: cgmath::Vector3<f32> = v2.position.into();
+
+        let uv0: cgmath::Vector2<f32> = v0.tex_coords.into();
+        let uv1: cgmath::Vector2<f32> = v1.tex_coords.into();
+        let uv2: cgmath::Vector2<f32> = v2.tex_coords.into();
+
+        let delta_pos1 = pos1 - pos0;
+        let delta_pos2 = pos2 - pos0;
+
+        let delta_uv1 = uv1 - uv0;
+        let delta_uv2 = uv2 - uv0;
+
+        // A near-zero determinant means this triangle's UVs are degenerate
+        // (e.g. identical placeholder UVs, or two vertices sharing a planar
+        // UV projection on a near-vertical face) — skip it instead of
+        // dividing by ~zero and producing a NaN tangent/bitangent.
+        let determinant = delta_uv1.x * delta_uv2.y - delta_uv1.y * delta_uv2.x;
+        if determinant.abs() < f32::EPSILON {
+            continue;
+        }
+
+        let r = 1.0 / determinant;
+        let tangent = (delta_pos1 * delta_uv2.y - delta_pos2 * delta_uv1.y) * r;
+        let bitangent = (delta_pos2 * delta_uv1.x - delta_pos1 * delta_uv2.x) * -r;
+
+        vertices[chunk[0] as usize].tangent =
+            (tangent + cgmath::Vector3::from(vertices[chunk[0] as usize].tangent)).into();
+        vertices[chunk[1] as usize].tangent =
+            (tangent + cgmath::Vector3::from(vertices[chunk[1] as usize].tangent)).into();
+        vertices[chunk[2] as usize].tangent =
+            (tangent + cgmath::Vector3::from(vertices[chunk[2] as usize].tangent)).into();
+        vertices[chunk[0] as usize].bitangent =
+            (bitangent + cgmath::Vector3::from(vertices[chunk[0] as usize].bitangent)).into();
+        vertices[chunk[1] as usize].bitangent =
+            (bitangent + cgmath::Vector3::from(vertices[chunk[1] as usize].bitangent)).into();
+        vertices[chunk[2] as usize].bitangent =
+            (bitangent + cgmath::Vector3::from(vertices[chunk[2] as usize].bitangent)).into();
+
+        triangles_included[chunk[0] as usize] += 1;
+        triangles_included[chunk[1] as usize] += 1;
+        triangles_included[chunk[2] as usize] += 1;
+    }
+
+    for (i, n) in triangles_included.into_iter().enumerate() {
+        if n == 0 {
+            // Every triangle touching this vertex was skipped above; leave
+            // its tangent/bitangent at the zeroed default instead of
+            // dividing by zero.
+            continue;
+        }
+        let denominator = 1.0 / n as f32;
+        let vertex = &mut vertices[i];
+        vertex.tangent = (cgmath::Vector3::from(vertex.tangent) * denominator).into();
+        vertex.bitangent = (cgmath::Vector3::from(vertex.bitangent) * denominator).into();
+    }
+}
+
 pub async fn load_model(
     file_name: &Path,
     device: &wgpu::Device,
     queue: &wgpu::Queue,
     layout: &wgpu::BindGroupLayout,
+) -> anyhow::Result<Model> {
+    match file_name.extension().and_then(|ext| ext.to_str()) {
+        Some("gltf") | Some("glb") => load_gltf(file_name, device, queue, layout).await,
+        _ => load_obj(file_name, device, queue, layout).await,
+    }
+}
+
+async fn load_obj(
+    file_name: &Path,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    layout: &wgpu::BindGroupLayout,
 ) -> anyhow::Result<Model> {
     let obj_text = load_string(file_name).await?;
     let obj_cursor = Cursor::new(obj_text);
@@ -124,6 +241,9 @@ pub async fn load_model(
     let meshes = models
         .into_iter()
         .map(|m| {
+            let has_normals = !m.mesh.normals.is_empty();
+            let has_texcoords = !m.mesh.texcoords.is_empty();
+
             let mut vertices = (0..m.mesh.positions.len() / 3)
                 .map(|i| ModelVertex {
                     position: [
@@ -131,69 +251,36 @@ pub async fn load_model(
                         m.mesh.positions[i * 3 + 1],
                         m.mesh.positions[i * 3 + 2],
                     ],
-                    tex_coords: [m.mesh.texcoords[i * 2], m.mesh.texcoords[i * 2 + 1]],
-                    normal: [
-                        m.mesh.normals[i * 3],
-                        m.mesh.normals[i * 3 + 1],
-                        m.mesh.normals[i * 3 + 2],
-                    ],
+                    tex_coords: if has_texcoords {
+                        [m.mesh.texcoords[i * 2], m.mesh.texcoords[i * 2 + 1]]
+                    } else {
+                        [0.0, 0.0]
+                    },
+                    normal: if has_normals {
+                        [
+                            m.mesh.normals[i * 3],
+                            m.mesh.normals[i * 3 + 1],
+                            m.mesh.normals[i * 3 + 2],
+                        ]
+                    } else {
+                        [0.0; 3]
+                    },
                     tangent: [0.0; 3],
                     bitangent: [0.0; 3],
                 })
                 .collect::<Vec<_>>();
 
             let indices = &m.mesh.indices;
-            let mut triangles_included = vec![0; vertices.len()];
-
-            for chunk in indices.chunks(3) {
-                let v0 = vertices[chunk[0] as usize];
-                let v1 = vertices[chunk[1] as usize];
-                let v2 = vertices[chunk[2] as usize];
-
-                let pos0: cgmath::Vector3<f32> = v0.position.into();
-                let pos1: cgmath::Vector3<f32> = v1.position.into();
-                let pos2: cgmath::Vector3<f32> = v2.position.into();
-
-                let uv0: cgmath::Vector2<f32> = v0.tex_coords.into();
-                let uv1: cgmath::Vector2<f32> = v1.tex_coords.into();
-                let uv2: cgmath::Vector2<f32> = v2.tex_coords.into();
-
-                let delta_pos1 = pos1 - pos0;
-                let delta_pos2 = pos2 - pos0;
-
-                let delta_uv1 = uv1 - uv0;
-                let delta_uv2 = uv2 - uv0;
-
-                let r = 1.0 / (delta_uv1.x * delta_uv2.y - delta_uv1.y * delta_uv2.x);
-                let tangent = (delta_pos1 * delta_uv2.y - delta_pos2 * delta_uv1.y) * r;
-                let bitangent = (delta_pos2 * delta_uv2.x - delta_pos1 * delta_uv2.x) * -r;
-
-                vertices[chunk[0] as usize].tangent =
-                    (tangent + cgmath::Vector3::from(vertices[chunk[0] as usize].tangent)).into();
-                vertices[chunk[1] as usize].tangent =
-                    (tangent + cgmath::Vector3::from(vertices[chunk[1] as usize].tangent)).into();
-                vertices[chunk[2] as usize].tangent =
-                    (tangent + cgmath::Vector3::from(vertices[chunk[2] as usize].tangent)).into();
-                vertices[chunk[0] as usize].bitangent = (bitangent
-                    + cgmath::Vector3::from(vertices[chunk[0] as usize].bitangent))
-                .into();
-                vertices[chunk[1] as usize].bitangent = (bitangent
-                    + cgmath::Vector3::from(vertices[chunk[1] as usize].bitangent))
-                .into();
-                vertices[chunk[2] as usize].bitangent = (bitangent
-                    + cgmath::Vector3::from(vertices[chunk[2] as usize].bitangent))
-                .into();
-
-                triangles_included[chunk[0] as usize] += 1;
-                triangles_included[chunk[1] as usize] += 1;
-                triangles_included[chunk[2] as usize] += 1;
+
+            if !has_normals {
+                compute_normals(&mut vertices, indices);
             }
 
-            for (i, n) in triangles_included.into_iter().enumerate() {
-                let denominator = 1.0 / n as f32;
-                let mut vertex = &mut vertices[i];
-                vertex.tangent = (cgmath::Vector3::from(vertex.tangent) * denominator).into();
-                vertex.bitangent = (cgmath::Vector3::from(vertex.bitangent) * denominator).into();
+            // Without real UVs there's no tangent space to derive; leave
+            // tangent/bitangent zeroed rather than dividing by a degenerate
+            // `delta_uv`, which would otherwise produce NaNs.
+            if has_texcoords {
+                compute_tangents(&mut vertices, indices);
             }
 
             let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -219,3 +306,213 @@ pub async fn load_model(
 
     Ok(Model { meshes, materials })
 }
+
+async fn load_gltf(
+    file_name: &Path,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    layout: &wgpu::BindGroupLayout,
+) -> anyhow::Result<Model> {
+    let gltf_bytes = load_binary(file_name).await?;
+    let gltf = gltf::Gltf::from_slice(&gltf_bytes)?;
+
+    let mut buffer_data = Vec::new();
+    for buffer in gltf.buffers() {
+        let data = match buffer.source() {
+            gltf::buffer::Source::Bin => gltf
+                .blob
+                .as_deref()
+                .ok_or_else(|| anyhow::anyhow!("glTF file references its GLB blob but has none"))?
+                .to_vec(),
+            gltf::buffer::Source::Uri(uri) => {
+                let buffer_path = file_name.parent().unwrap().join(uri);
+                load_binary(&buffer_path).await?
+            }
+        };
+        buffer_data.push(data);
+    }
+
+    let mut materials = Vec::new();
+    for material in gltf.materials() {
+        let pbr = material.pbr_metallic_roughness();
+
+        let diffuse_texture = match pbr.base_color_texture() {
+            Some(info) => {
+                load_gltf_texture(
+                    &info.texture(),
+                    file_name,
+                    &buffer_data,
+                    false,
+                    device,
+                    queue,
+                )
+                .await?
+            }
+            None => {
+                let [r, g, b, a] = pbr.base_color_factor();
+                solid_color_texture(
+                    device,
+                    queue,
+                    [
+                        (r * 255.0) as u8,
+                        (g * 255.0) as u8,
+                        (b * 255.0) as u8,
+                        (a * 255.0) as u8,
+                    ],
+                    false,
+                    "glTF Default Base Color",
+                )?
+            }
+        };
+
+        let normal_texture = match material.normal_texture() {
+            Some(info) => {
+                load_gltf_texture(
+                    &info.texture(),
+                    file_name,
+                    &buffer_data,
+                    true,
+                    device,
+                    queue,
+                )
+                .await?
+            }
+            // Flat tangent-space normal (0, 0, 1), encoded the same way a
+            // normal map image would be.
+            None => solid_color_texture(
+                device,
+                queue,
+                [128, 128, 255, 255],
+                true,
+                "glTF Default Normal",
+            )?,
+        };
+
+        materials.push(Material::new(
+            device,
+            material.name().unwrap_or("glTF Material"),
+            diffuse_texture,
+            normal_texture,
+            layout,
+        ));
+    }
+
+    let mut meshes = Vec::new();
+    for mesh in gltf.meshes() {
+        for primitive in mesh.primitives() {
+            let reader = primitive.reader(|buffer| Some(&buffer_data[buffer.index()]));
+
+            let positions = reader
+                .read_positions()
+                .ok_or_else(|| anyhow::anyhow!("glTF primitive is missing POSITION"))?
+                .collect::<Vec<_>>();
+
+            let normals = reader
+                .read_normals()
+                .map(|iter| iter.collect::<Vec<_>>())
+                .unwrap_or_else(|| vec![[0.0, 0.0, 1.0]; positions.len()]);
+
+            let has_texcoords = reader.read_tex_coords(0).is_some();
+            let tex_coords = reader
+                .read_tex_coords(0)
+                .map(|iter| iter.into_f32().collect::<Vec<_>>())
+                .unwrap_or_else(|| vec![[0.0, 0.0]; positions.len()]);
+
+            let tangents = reader.read_tangents().map(|iter| iter.collect::<Vec<_>>());
+
+            let indices = reader
+                .read_indices()
+                .map(|iter| iter.into_u32().collect::<Vec<_>>())
+                .unwrap_or_else(|| (0..positions.len() as u32).collect());
+
+            let mut vertices = (0..positions.len())
+                .map(|i| ModelVertex {
+                    position: positions[i],
+                    tex_coords: tex_coords[i],
+                    normal: normals[i],
+                    tangent: [0.0; 3],
+                    bitangent: [0.0; 3],
+                })
+                .collect::<Vec<_>>();
+
+            if let Some(tangents) = tangents {
+                // The file already supplies tangents; derive the bitangent
+                // from the handedness in `tangent.w` instead of re-deriving
+                // both from UV derivatives.
+                for (i, tangent) in tangents.into_iter().enumerate() {
+                    let normal = cgmath::Vector3::from(vertices[i].normal);
+                    let t = cgmath::Vector3::new(tangent[0], tangent[1], tangent[2]);
+                    vertices[i].tangent = t.into();
+                    vertices[i].bitangent = (normal.cross(t) * tangent[3]).into();
+                }
+            } else if has_texcoords {
+                compute_tangents(&mut vertices, &indices);
+            }
+
+            let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(&format!("{:?} Vertex Buffer", file_name)),
+                contents: bytemuck::cast_slice(&vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+            let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(&format!("{:?} Index Buffer", file_name)),
+                contents: bytemuck::cast_slice(&indices),
+                usage: wgpu::BufferUsages::INDEX,
+            });
+
+            meshes.push(Mesh {
+                name: mesh.name().unwrap_or("glTF Mesh").to_string(),
+                vertex_buffer,
+                index_buffer,
+                num_elements: indices.len() as u32,
+                material: primitive.material().index().unwrap_or(0),
+            });
+        }
+    }
+
+    Ok(Model { meshes, materials })
+}
+
+/// Builds a 1x1 texture of a solid color, for glTF material slots with no texture.
+fn solid_color_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    rgba: [u8; 4],
+    is_normal_map: bool,
+    label: &str,
+) -> anyhow::Result<Texture> {
+    let image = image::RgbaImage::from_pixel(1, 1, image::Rgba(rgba));
+    let mut bytes = Vec::new();
+    image::DynamicImage::ImageRgba8(image)
+        .write_to(&mut Cursor::new(&mut bytes), image::ImageOutputFormat::Png)?;
+
+    Texture::from_bytes(device, queue, &bytes, label, is_normal_map)
+}
+
+async fn load_gltf_texture(
+    texture: &gltf::Texture<'_>,
+    file_name: &Path,
+    buffer_data: &[Vec<u8>],
+    is_normal_map: bool,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+) -> anyhow::Result<Texture> {
+    match texture.source().source() {
+        gltf::image::Source::Uri { uri, .. } => {
+            let texture_path = file_name.parent().unwrap().join(uri);
+            load_texture(&texture_path, is_normal_map, device, queue).await
+        }
+        gltf::image::Source::View { view, .. } => {
+            let buffer = &buffer_data[view.buffer().index()];
+            let start = view.offset();
+            let end = start + view.length();
+            Texture::from_bytes(
+                device,
+                queue,
+                &buffer[start..end],
+                texture.name().unwrap_or("glTF Texture"),
+                is_normal_map,
+            )
+        }
+    }
+}